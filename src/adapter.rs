@@ -0,0 +1,67 @@
+use std::{convert::Infallible, fmt, str::FromStr};
+
+use serde::Deserialize;
+
+/// Graphics API to render with, for working around driver bugs in the default choice.
+#[derive(Deserialize, clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Backend {
+    Vulkan,
+    Dx12,
+    Metal,
+    Gl,
+    Webgpu,
+}
+
+impl From<Backend> for wgpu::Backends {
+    fn from(backend: Backend) -> Self {
+        match backend {
+            Backend::Vulkan => wgpu::Backends::VULKAN,
+            Backend::Dx12 => wgpu::Backends::DX12,
+            Backend::Metal => wgpu::Backends::METAL,
+            Backend::Gl => wgpu::Backends::GL,
+            Backend::Webgpu => wgpu::Backends::BROWSER_WEBGPU,
+        }
+    }
+}
+
+/// Picks one of the adapters reported by `wgpu::Instance::enumerate_adapters`,
+/// either by its position in that list or by (part of) its name.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum AdapterSelector {
+    Index(usize),
+    Name(String),
+}
+
+impl FromStr for AdapterSelector {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s.parse() {
+            Ok(index) => Self::Index(index),
+            Err(_) => Self::Name(s.to_owned()),
+        })
+    }
+}
+
+impl fmt::Display for AdapterSelector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Index(index) => write!(f, "#{index}"),
+            Self::Name(name) => write!(f, "\"{name}\""),
+        }
+    }
+}
+
+/// Print every adapter the instance can see, in the order `--adapter` indexes them.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn list_adapters(instance: &wgpu::Instance) {
+    for (index, adapter) in instance.enumerate_adapters(wgpu::Backends::all()).iter().enumerate() {
+        let info = adapter.get_info();
+        println!(
+            "{index}: {} ({:?}, {:?}, driver: {} {})",
+            info.name, info.backend, info.device_type, info.driver, info.driver_info
+        );
+    }
+}
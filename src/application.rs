@@ -14,6 +14,8 @@ use anyhow::{Context, Result};
 use wgpu::RenderPipeline;
 use winit::{dpi::PhysicalSize, window::Window};
 
+use crate::config::GpuConfig;
+
 pub struct Application {
     // surface_config: wgpu::SurfaceConfiguration,
     // surface: wgpu::Surface<'static>,
@@ -23,9 +25,15 @@ pub struct Application {
 }
 
 impl Application {
-    pub async fn new(window: Arc<Window>, size: PhysicalSize<u32>) -> Result<Self> {
+    pub async fn new(window: Arc<Window>, size: PhysicalSize<u32>, gpu: &GpuConfig) -> Result<Self> {
+        // Remove this once step 1 below respects `gpu.backend`
+        if let Some(backend) = gpu.backend {
+            log::warn!("Ignoring backend {backend:?}: it only applies to --list-adapters so far");
+        }
+
         // 1. We first must create a `wgpu::Instance`.
         // This is the entrypoint to all communication with wgpu.
+        // Only enable the backends in `gpu.backends()`, so users can pick one with `--backend`.
 
         // 2. Next, we create our surface through the instance we created above.
         // For this, we must pass a window for the surface to target.
@@ -37,6 +45,8 @@ impl Application {
         // with two GPUs, we get the more powerful one.
         // Note that requesting an adapter is an asynchronous operation that must be awaited.
         // If no adapter matches our request options, we receive `None`.
+        // Picking a specific adapter (`gpu.adapter`, via `--adapter`) is rejected by
+        // `GpuConfig::validate` for now, so requesting one as described is all we need.
 
         // 4. While an adapter represents the a physical GPU, we also need a logical handle
         // to this GPU that enforces feature and memory limitations and is responsible for
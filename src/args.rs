@@ -0,0 +1,116 @@
+use std::path::PathBuf;
+
+use clap::Parser;
+
+use crate::{
+    adapter::{AdapterSelector, Backend},
+    config::Config,
+    i18n::Language,
+};
+
+/// Startup options for the raytracer.
+/// Anything not given here falls back to the config file.
+#[derive(Parser, Debug)]
+#[command(version, about)]
+pub struct Args {
+    /// Config file to read defaults from [default: raytracer.toml, if it exists]
+    #[arg(long)]
+    pub config: Option<PathBuf>,
+
+    /// Initial window width in logical pixels
+    #[arg(long)]
+    pub width: Option<u32>,
+
+    /// Initial window height in logical pixels
+    #[arg(long)]
+    pub height: Option<u32>,
+
+    /// Graphics API to render with
+    #[arg(long, value_enum, env = "RAYTRACER_BACKEND")]
+    pub backend: Option<Backend>,
+
+    /// Adapter to render with, by index or (part of its) name
+    #[arg(long, value_name = "INDEX|NAME")]
+    pub adapter: Option<AdapterSelector>,
+
+    /// Upper limit for frames per second
+    #[arg(long)]
+    pub max_fps: Option<u32>,
+
+    /// Upper limit for frames per second while the window doesn't have focus
+    #[arg(long)]
+    pub unfocused_max_fps: Option<u32>,
+
+    /// Stop drawing after this many seconds without input
+    #[arg(long, value_name = "SECONDS")]
+    pub idle_timeout: Option<f64>,
+
+    /// Pace frames to an even fraction of the monitor's refresh rate (`=false` turns it off)
+    #[arg(long, num_args = 0..=1, default_missing_value = "true", require_equals = true)]
+    pub match_refresh_rate: Option<bool>,
+
+    /// Language for user-facing text [default: system locale]
+    #[arg(long, value_enum)]
+    pub language: Option<Language>,
+
+    /// Print the available adapters and exit
+    #[cfg(not(target_arch = "wasm32"))]
+    #[arg(long)]
+    pub list_adapters: bool,
+}
+
+impl Args {
+    pub fn load() -> Self {
+        // There is no command line on the web, so only the defaults apply there
+        #[cfg(not(target_arch = "wasm32"))]
+        return Self::parse();
+        #[cfg(target_arch = "wasm32")]
+        return Self::parse_from([env!("CARGO_PKG_NAME")]);
+    }
+
+    pub fn apply(&self, config: &mut Config) {
+        if let Some(width) = self.width {
+            config.window.width = width;
+        }
+        if let Some(height) = self.height {
+            config.window.height = height;
+        }
+        if let Some(backend) = self.backend {
+            config.gpu.backend = Some(backend);
+        }
+        if let Some(adapter) = &self.adapter {
+            config.gpu.adapter = Some(adapter.clone());
+        }
+        if let Some(max_fps) = self.max_fps {
+            config.frame.max_fps = Some(max_fps);
+        }
+        if let Some(unfocused_max_fps) = self.unfocused_max_fps {
+            config.frame.unfocused_max_fps = Some(unfocused_max_fps);
+        }
+        if let Some(idle_timeout) = self.idle_timeout {
+            config.frame.idle_timeout = Some(idle_timeout);
+        }
+        if let Some(match_refresh_rate) = self.match_refresh_rate {
+            config.frame.match_refresh_rate = match_refresh_rate;
+        }
+        if let Some(language) = self.language {
+            config.ui.language = Some(language);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn match_refresh_rate_overrides_config_both_ways() {
+        let mut config = Config::default();
+        Args::parse_from(["raytracer", "--match-refresh-rate"]).apply(&mut config);
+        assert!(config.frame.match_refresh_rate);
+        Args::parse_from(["raytracer"]).apply(&mut config);
+        assert!(config.frame.match_refresh_rate);
+        Args::parse_from(["raytracer", "--match-refresh-rate=false"]).apply(&mut config);
+        assert!(!config.frame.match_refresh_rate);
+    }
+}
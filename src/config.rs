@@ -0,0 +1,198 @@
+use std::{fs, io, path::Path, time::Duration};
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde::Deserialize;
+
+use crate::{
+    adapter::{AdapterSelector, Backend},
+    i18n::Language,
+};
+
+/// Settings read from `raytracer.toml` at startup.
+/// Command-line arguments take precedence over anything set here.
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub window: WindowConfig,
+    pub gpu: GpuConfig,
+    pub ui: UiConfig,
+    pub frame: FrameConfig,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct WindowConfig {
+    /// Initial window width in logical pixels
+    pub width: u32,
+    /// Initial window height in logical pixels
+    pub height: u32,
+}
+
+impl Default for WindowConfig {
+    fn default() -> Self {
+        Self {
+            width: 1280,
+            height: 720,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct GpuConfig {
+    /// Graphics API to use. `None` allows all of them.
+    pub backend: Option<Backend>,
+    /// Adapter to render with, by index or name. `None` lets wgpu pick one.
+    pub adapter: Option<AdapterSelector>,
+}
+
+impl GpuConfig {
+    /// The backends to create the `wgpu::Instance` with
+    pub fn backends(&self) -> wgpu::Backends {
+        self.backend.map_or(wgpu::Backends::all(), Into::into)
+    }
+
+    fn validate(&self) -> Result<()> {
+        // Better to refuse than to quietly render on some other GPU than the one asked for
+        if let Some(adapter) = &self.adapter {
+            bail!("gpu.adapter (--adapter) {adapter} is not supported yet: `Application::new` doesn't select adapters");
+        }
+        Ok(())
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct UiConfig {
+    /// Language for user-facing text. `None` follows the system locale.
+    pub language: Option<Language>,
+}
+
+impl UiConfig {
+    pub fn language(&self) -> Language {
+        self.language.unwrap_or_else(Language::from_system)
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct FrameConfig {
+    /// Upper limit for frames per second. `None` draws as often as presenting allows.
+    pub max_fps: Option<u32>,
+    /// Upper limit for frames per second while the window doesn't have focus
+    pub unfocused_max_fps: Option<u32>,
+    /// Seconds without input after which we stop drawing new frames. `None` never idles.
+    pub idle_timeout: Option<f64>,
+    /// Pace frames to an even fraction of the monitor's refresh rate
+    pub match_refresh_rate: bool,
+}
+
+impl FrameConfig {
+    /// Make sure the values can be turned into `Duration`s without panicking.
+    fn validate(&self) -> Result<()> {
+        for (key, fps) in [
+            ("frame.max_fps (--max-fps)", self.max_fps),
+            ("frame.unfocused_max_fps (--unfocused-max-fps)", self.unfocused_max_fps),
+        ] {
+            if fps == Some(0) {
+                bail!("invalid {key} 0: leave it out for no limit");
+            }
+        }
+        if let Some(idle_timeout) = self.idle_timeout {
+            Duration::try_from_secs_f64(idle_timeout).map_err(|_| {
+                anyhow!("invalid frame.idle_timeout (--idle-timeout) {idle_timeout}: expected a non-negative number of seconds")
+            })?;
+        }
+        Ok(())
+    }
+}
+
+/// Read when no config file is given on the command line
+const DEFAULT_PATH: &str = "raytracer.toml";
+
+impl Config {
+    /// Load the config file the user asked for, or `raytracer.toml` if they didn't.
+    pub fn load(path: Option<&Path>) -> Result<Self> {
+        match path {
+            Some(path) => Self::read(path, true),
+            None => Self::read(Path::new(DEFAULT_PATH), false),
+        }
+    }
+
+    fn read(path: &Path, required: bool) -> Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(source) => {
+                toml::from_str(&source).with_context(|| format!("invalid config file {}", path.display()))
+            }
+            // Without the default file (or any filesystem at all, on the web) we just use the defaults.
+            // A file the user named explicitly has to exist though, so typos don't go unnoticed.
+            Err(e) if !required && matches!(e.kind(), io::ErrorKind::NotFound | io::ErrorKind::Unsupported) => {
+                Ok(Self::default())
+            }
+            Err(e) => Err(e).with_context(|| format!("could not read config file {}", path.display())),
+        }
+    }
+
+    /// Check the settings once the command line has been applied, so bad values are reported
+    /// at startup instead of causing a panic later on.
+    pub fn validate(&self) -> Result<()> {
+        self.gpu.validate()?;
+        self.frame.validate()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_config_file_is_only_an_error_when_given_explicitly() {
+        let path = std::env::temp_dir().join("raytracer-missing-config.toml");
+        assert!(Config::read(&path, false).is_ok());
+        let error = Config::read(&path, true).unwrap_err();
+        assert!(error.to_string().contains("raytracer-missing-config.toml"), "{error}");
+    }
+
+    #[test]
+    fn explicit_config_file_is_read() {
+        let path = std::env::temp_dir().join(format!("raytracer-config-{}.toml", std::process::id()));
+        fs::write(&path, "[window]\nwidth = 640\n").unwrap();
+        let config = Config::load(Some(&path));
+        fs::remove_file(&path).unwrap();
+        assert_eq!(config.unwrap().window.width, 640);
+    }
+
+    #[test]
+    fn rejects_adapter_until_supported() {
+        let config: Config = toml::from_str("[gpu]\nadapter = 0").unwrap();
+        let error = config.validate().unwrap_err();
+        assert!(error.to_string().contains("gpu.adapter"), "{error}");
+    }
+
+    #[test]
+    fn rejects_invalid_idle_timeout() {
+        for value in ["-1.0", "nan", "inf", "1e30"] {
+            let config: Config = toml::from_str(&format!("[frame]\nidle_timeout = {value}")).unwrap();
+            let error = config.validate().unwrap_err();
+            assert!(error.to_string().contains("frame.idle_timeout"), "{value}: {error}");
+        }
+    }
+
+    #[test]
+    fn rejects_zero_fps_limits() {
+        for key in ["max_fps", "unfocused_max_fps"] {
+            let config: Config = toml::from_str(&format!("[frame]\n{key} = 0")).unwrap();
+            let error = config.validate().unwrap_err();
+            assert!(error.to_string().contains(&format!("frame.{key}")), "{error}");
+        }
+    }
+
+    #[test]
+    fn accepts_valid_idle_timeout() {
+        for value in ["0.0", "0.5", "30.0"] {
+            let config: Config = toml::from_str(&format!("[frame]\nidle_timeout = {value}")).unwrap();
+            assert!(config.validate().is_ok(), "{value}");
+        }
+        assert!(Config::default().validate().is_ok());
+    }
+}
@@ -0,0 +1,118 @@
+// Crash reports for the native build.
+// On panic (which includes uncaptured wgpu device errors) we write a directory with
+// everything needed to make sense of a bug report: the panic itself, the most recent
+// log lines and the settings the application was started with.
+use std::{
+    backtrace::Backtrace,
+    collections::VecDeque,
+    fs,
+    panic::PanicHookInfo,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::Result;
+
+use crate::{config::Config, i18n::Text};
+
+const RECENT_LOG_LINES: usize = 200;
+/// Kept for crash reports even when `RUST_LOG` prints less
+const RECORDED_LEVEL: log::Level = log::Level::Info;
+
+static RECENT_LOG: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+
+/// Wraps env_logger so the last few records are kept around for crash reports.
+struct RecordingLogger {
+    inner: env_logger::Logger,
+}
+
+impl log::Log for RecordingLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= RECORDED_LEVEL || self.inner.enabled(metadata)
+    }
+
+    fn log(&self, record: &log::Record) {
+        // Only print what `RUST_LOG` asks for, but keep everything for the crash report
+        if self.inner.matches(record) {
+            self.inner.log(record);
+        }
+
+        let seconds = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs_f64();
+        let line = format!("[{seconds:.3} {} {}] {}", record.level(), record.target(), record.args());
+        if let Ok(mut recent) = RECENT_LOG.lock() {
+            if recent.len() == RECENT_LOG_LINES {
+                recent.pop_front();
+            }
+            recent.push_back(line);
+        }
+    }
+
+    fn flush(&self) {
+        self.inner.flush();
+    }
+}
+
+/// Set up logging like `env_logger::init` does, but remembering recent lines.
+/// Without `RUST_LOG`, warnings are shown too, since some of them tell users about settings we ignore.
+pub fn init_logger() {
+    let inner = env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("warn")).build();
+    log::set_max_level(inner.filter().max(RECORDED_LEVEL.to_level_filter()));
+    log::set_boxed_logger(Box::new(RecordingLogger { inner })).expect("logger already initialized");
+}
+
+/// Write a crash report whenever the application panics, then continue with the default panic output.
+pub fn install_panic_hook(config: &Config) {
+    let language = config.ui.language();
+    let config = format!("{config:#?}");
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        match write_report(info, &config) {
+            Ok(dir) => eprintln!("{} {}", language.text(Text::CrashReportWritten), dir.display()),
+            Err(e) => eprintln!("{}: {e:#}", language.text(Text::CrashReportFailed)),
+        }
+        default_hook(info);
+    }));
+}
+
+fn write_report(info: &PanicHookInfo, config: &str) -> Result<PathBuf> {
+    // Nanoseconds and the process id keep crashes in the same second from overwriting each other
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?;
+    let name = format!(
+        "{}-{:09}-{}",
+        timestamp.as_secs(),
+        timestamp.subsec_nanos(),
+        std::process::id()
+    );
+    // An absolute path, so it's clear where to look no matter where we were started from
+    let dir = std::path::absolute(Path::new("crash-reports"))?.join(name);
+    fs::create_dir_all(&dir)?;
+
+    fs::write(
+        dir.join("panic.txt"),
+        format!("{info}\n\nBacktrace:\n{}", Backtrace::force_capture()),
+    )?;
+    fs::write(
+        dir.join("system.txt"),
+        format!(
+            "{} {}\nos: {}\narch: {}\n",
+            env!("CARGO_PKG_NAME"),
+            env!("CARGO_PKG_VERSION"),
+            std::env::consts::OS,
+            std::env::consts::ARCH,
+        ),
+    )?;
+    fs::write(dir.join("config.txt"), config)?;
+
+    // The panic may have happened while logging, so don't wait for the lock
+    let log = match RECENT_LOG.try_lock() {
+        Ok(recent) => recent.iter().map(|line| format!("{line}\n")).collect(),
+        Err(_) => String::from("(log unavailable)\n"),
+    };
+    fs::write(dir.join("log.txt"), log)?;
+
+    Ok(dir)
+}
@@ -0,0 +1,56 @@
+use std::{error::Error, fmt};
+
+use crate::i18n::{Language, Text};
+
+/// Errors that stop the raytracer. They are reported to the user instead of panicking,
+/// which on the web would only ever show up in the developer console.
+#[derive(Debug)]
+pub enum AppError {
+    /// The window could not be created
+    Window(winit::error::OsError),
+    /// The canvas could not be added to the web page, with what went wrong
+    #[cfg(target_arch = "wasm32")]
+    WebPage(&'static str),
+    /// Setting up the GPU resources in `Application::new` failed
+    Startup(anyhow::Error),
+    /// Rendering a frame failed in a way we can't recover from
+    Render(wgpu::SurfaceError),
+}
+
+impl AppError {
+    /// The error as shown to the user. The details come from wgpu or the OS and stay as they are.
+    pub fn describe(&self, language: Language) -> String {
+        match self {
+            Self::Window(e) => format!("{}: {e}", language.text(Text::WindowError)),
+            #[cfg(target_arch = "wasm32")]
+            Self::WebPage(what) => format!("{}: {what}", language.text(Text::WebPageError)),
+            Self::Startup(e) => format!("{}: {e:#}", language.text(Text::StartupError)),
+            Self::Render(e) => format!("{}: {e}", language.text(Text::RenderError)),
+        }
+    }
+}
+
+/// English, for the log
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Window(e) => write!(f, "could not create window: {e}"),
+            #[cfg(target_arch = "wasm32")]
+            Self::WebPage(what) => write!(f, "could not set up the web page: {what}"),
+            Self::Startup(e) => write!(f, "could not set up rendering: {e:#}"),
+            Self::Render(e) => write!(f, "could not render frame: {e}"),
+        }
+    }
+}
+
+impl Error for AppError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Window(e) => Some(e),
+            #[cfg(target_arch = "wasm32")]
+            Self::WebPage(_) => None,
+            Self::Startup(e) => Some(e.as_ref()),
+            Self::Render(e) => Some(e),
+        }
+    }
+}
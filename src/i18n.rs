@@ -0,0 +1,65 @@
+use serde::Deserialize;
+
+/// Languages the user-facing text is available in.
+#[derive(Deserialize, clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Language {
+    #[default]
+    En,
+    It,
+}
+
+/// Every piece of text shown to the user.
+/// Log messages are for developers and stay in English.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Text {
+    WindowTitle,
+    FatalError,
+    WindowError,
+    WebPageError,
+    StartupError,
+    RenderError,
+    CrashReportWritten,
+    CrashReportFailed,
+    DrawTime,
+    Idle,
+}
+
+impl Language {
+    /// The language of the system locale (the browser's language on the web),
+    /// falling back to English if we don't have it.
+    pub fn from_system() -> Self {
+        let locale = sys_locale::get_locale().unwrap_or_default();
+        // Locales look like `it-IT` or `it_IT.UTF-8`, we only care about the language part
+        match locale.split(['-', '_', '.', '@']).next().unwrap_or_default() {
+            "it" => Self::It,
+            _ => Self::En,
+        }
+    }
+
+    pub fn text(self, text: Text) -> &'static str {
+        match (self, text) {
+            (Self::En, Text::WindowTitle) => "wgpu raytracer",
+            (Self::En, Text::FatalError) => "The raytracer stopped because of an error",
+            (Self::En, Text::WindowError) => "Could not create the window",
+            (Self::En, Text::WebPageError) => "Could not set up the web page",
+            (Self::En, Text::StartupError) => "Could not set up rendering",
+            (Self::En, Text::RenderError) => "Could not draw a frame",
+            (Self::En, Text::CrashReportWritten) => "A crash report was written to",
+            (Self::En, Text::CrashReportFailed) => "Failed to write crash report",
+            (Self::En, Text::DrawTime) => "drawing",
+            (Self::En, Text::Idle) => "idle",
+
+            (Self::It, Text::WindowTitle) => "Raytracer wgpu",
+            (Self::It, Text::FatalError) => "Il raytracer si è fermato a causa di un errore",
+            (Self::It, Text::WindowError) => "Impossibile creare la finestra",
+            (Self::It, Text::WebPageError) => "Impossibile preparare la pagina web",
+            (Self::It, Text::StartupError) => "Impossibile preparare il rendering",
+            (Self::It, Text::RenderError) => "Impossibile disegnare un fotogramma",
+            (Self::It, Text::CrashReportWritten) => "È stato scritto un rapporto sull'arresto anomalo in",
+            (Self::It, Text::CrashReportFailed) => "Impossibile scrivere il rapporto sull'arresto anomalo",
+            (Self::It, Text::DrawTime) => "disegno",
+            (Self::It, Text::Idle) => "inattivo",
+        }
+    }
+}
@@ -2,142 +2,283 @@ use std::sync::Arc;
 
 use anyhow::Result;
 use application::Application;
+use args::Args;
+use config::{Config, GpuConfig};
+use error::AppError;
+use i18n::Text;
+use pacing::{FramePacer, NextFrame};
+use stats::FrameStats;
+use web_time::Instant;
 use winit::{
     application::ApplicationHandler,
-    dpi::LogicalSize,
+    dpi::{LogicalSize, Size},
     event::WindowEvent,
-    event_loop::{EventLoop, EventLoopProxy},
+    event_loop::{ControlFlow, EventLoop, EventLoopProxy},
     window::Window,
 };
 
+mod adapter;
 mod application;
+mod args;
+mod config;
+#[cfg(not(target_arch = "wasm32"))]
+mod crash;
+mod error;
+mod i18n;
+mod pacing;
+mod platform;
+mod stats;
 
 fn main() -> Result<()> {
     #[cfg(not(target_arch = "wasm32"))]
-    env_logger::init();
+    crash::init_logger();
     #[cfg(target_arch = "wasm32")]
     {
         console_log::init().expect("could not initialize logger");
         std::panic::set_hook(Box::new(console_error_panic_hook::hook));
     }
 
+    let args = Args::load();
+    let mut config = Config::load(args.config.as_deref())?;
+    args.apply(&mut config);
+    config.validate()?;
+    #[cfg(not(target_arch = "wasm32"))]
+    crash::install_panic_hook(&config);
+
+    #[cfg(not(target_arch = "wasm32"))]
+    if args.list_adapters {
+        adapter::list_adapters(&wgpu::Instance::new(wgpu::InstanceDescriptor {
+            backends: config.gpu.backends(),
+            ..Default::default()
+        }));
+        return Ok(());
+    }
+
     let event_loop = EventLoop::with_user_event().build()?;
 
-    let mut app = ApplicationWindow::new(&event_loop);
+    let mut app = ApplicationWindow::new(&event_loop, config);
     event_loop.run_app(&mut app)?;
+    if app.failed {
+        // The error has already been reported by `ApplicationWindow::fail`
+        std::process::exit(1);
+    }
 
     Ok(())
 }
 
+/// Reconfigure a lost surface this many times in a row before recreating the whole application
+const RECONFIGURE_ATTEMPTS: u32 = 3;
+/// Give up once this many frames in a row failed because the surface was lost
+const MAX_LOST_FRAMES: u32 = 3 * RECONFIGURE_ATTEMPTS;
+
 pub enum UserEvent {
     ApplicationCreated(Application),
+    Failed(AppError),
 }
 
 pub struct ApplicationWindow {
     app: Option<Application>,
     window: Option<Arc<Window>>,
+    config: Config,
+    pacer: FramePacer,
+    stats: FrameStats,
+    suspended: bool,
     close_requested: bool,
+    failed: bool,
+    /// Frames in a row that failed with `SurfaceError::Lost`
+    lost_frames: u32,
     event_proxy: EventLoopProxy<UserEvent>,
 }
 
 impl ApplicationWindow {
-    pub fn new(event_loop: &EventLoop<UserEvent>) -> Self {
+    pub fn new(event_loop: &EventLoop<UserEvent>, config: Config) -> Self {
         Self {
             window: None,
             app: None,
+            pacer: FramePacer::new(&config.frame),
+            stats: FrameStats::new(),
+            suspended: false,
+            config,
             close_requested: false,
+            failed: false,
+            lost_frames: 0,
             event_proxy: event_loop.create_proxy(),
         }
     }
+
+    /// Start creating the `Application` for `window`.
+    /// It replaces the current one (if any) once it arrives as `UserEvent::ApplicationCreated`.
+    /// Pass the window's physical size when it already has one, converting back and forth
+    /// between logical and physical pixels can be off by one at fractional scale factors.
+    fn spawn_application(&self, window: Arc<Window>, size: Size) {
+        let gpu = self.config.gpu.clone();
+        let event_proxy = self.event_proxy.clone();
+        #[cfg(not(target_arch = "wasm32"))]
+        futures::executor::block_on(create_application(window, size, gpu, event_proxy));
+        #[cfg(target_arch = "wasm32")]
+        wasm_bindgen_futures::spawn_local(create_application(window, size, gpu, event_proxy));
+    }
+
+    /// Tell the user about an error we can't recover from and shut down.
+    fn fail(&mut self, error: AppError) {
+        log::error!("{error}");
+        let language = self.config.ui.language();
+        let headline = language.text(Text::FatalError);
+        let message = error.describe(language);
+        // The log may be turned off, so tell the user directly as well
+        #[cfg(not(target_arch = "wasm32"))]
+        eprintln!("{headline}: {message}");
+        #[cfg(target_arch = "wasm32")]
+        if let Some(web_window) = web_sys::window() {
+            let _ = web_window.alert_with_message(&format!("{headline}:\n\n{message}"));
+        }
+        self.app = None;
+        self.close_requested = true;
+        self.failed = true;
+    }
 }
 
-async fn create_application(window: Arc<Window>, size: LogicalSize<u32>, event_proxy: EventLoopProxy<UserEvent>) {
-    let size = size.to_physical(window.scale_factor());
+async fn create_application(
+    window: Arc<Window>,
+    size: Size,
+    gpu: GpuConfig,
+    event_proxy: EventLoopProxy<UserEvent>,
+) {
+    let size = size.to_physical::<u32>(window.scale_factor());
     log::info!("Initial size: {}x{}", size.width, size.height);
-    let app = Application::new(window, size)
-        .await
-        .expect("creation of application failed");
-    event_proxy
-        .send_event(UserEvent::ApplicationCreated(app))
-        .map_err(|_| "sending created application failed")
-        .unwrap();
+    let event = match Application::new(window, size, &gpu).await {
+        Ok(app) => UserEvent::ApplicationCreated(app),
+        Err(e) => UserEvent::Failed(AppError::Startup(e)),
+    };
+    if event_proxy.send_event(event).is_err() {
+        log::warn!("Event loop closed before the application was created");
+    }
+}
+
+/// On wasm, append the window's canvas to the document body and keep it as large as the
+/// browser window. Returns the initial size.
+#[cfg(target_arch = "wasm32")]
+fn attach_canvas(window: &Arc<Window>) -> Result<LogicalSize<u32>, AppError> {
+    use wasm_bindgen::JsCast;
+    use winit::platform::web::WindowExtWebSys;
+
+    let canvas = window
+        .canvas()
+        .ok_or(AppError::WebPage("couldn't retrieve canvas"))?;
+    let web_window = web_sys::window().ok_or(AppError::WebPage("couldn't retrieve website window"))?;
+    let body = web_window
+        .document()
+        .and_then(|doc| doc.body())
+        .ok_or(AppError::WebPage("couldn't retrieve document body"))?;
+    body.append_child(&web_sys::Element::from(canvas))
+        .map_err(|_| AppError::WebPage("couldn't append canvas to body"))?;
+
+    let window_size =
+        browser_window_size(&web_window).ok_or(AppError::WebPage("couldn't get the website window size"))?;
+    let _ = window.request_inner_size(window_size);
+
+    let window = window.clone();
+    let resize_closure = wasm_bindgen::closure::Closure::wrap(Box::new(move |_e: web_sys::Event| {
+        log::info!("bres {} {}", body.client_width(), body.client_height());
+        // Keep the current size if the browser can't tell us the new one
+        if let Some(size) = web_sys::window().as_ref().and_then(browser_window_size) {
+            let _ = window.request_inner_size(size);
+        }
+    }) as Box<dyn FnMut(_)>);
+    web_window
+        .add_event_listener_with_callback("resize", resize_closure.as_ref().unchecked_ref())
+        .map_err(|_| AppError::WebPage("couldn't listen for website window resizes"))?;
+    resize_closure.forget();
+
+    Ok(window_size)
+}
+
+#[cfg(target_arch = "wasm32")]
+fn browser_window_size(web_window: &web_sys::Window) -> Option<LogicalSize<u32>> {
+    Some(LogicalSize::new(
+        web_window.inner_width().ok()?.as_f64()? as u32,
+        web_window.inner_height().ok()?.as_f64()? as u32,
+    ))
 }
 
 impl ApplicationHandler<UserEvent> for ApplicationWindow {
     fn resumed(&mut self, event_loop: &winit::event_loop::ActiveEventLoop) {
-        if self.window.is_some() {
+        if let Some(window) = &self.window {
+            // Coming back from `suspended`, the surface has to be created again
+            if self.suspended {
+                self.suspended = false;
+                let size = window.inner_size().into();
+                self.spawn_application(window.clone(), size);
+            }
             return;
         }
 
-        let size = LogicalSize::new(1280, 720);
+        let size = LogicalSize::new(self.config.window.width, self.config.window.height);
         let window_attributes = Window::default_attributes()
-            .with_title("wgpu raytracer")
+            .with_title(self.config.ui.language().text(Text::WindowTitle))
             .with_inner_size(size)
-            .with_min_inner_size(LogicalSize::new(800, 600));
-        let window = Arc::new(event_loop.create_window(window_attributes).unwrap()); // needed for resize closure on web
+            .with_min_inner_size(LogicalSize::new(800, 600))
+            .with_window_icon(platform::window_icon());
+        let window = match event_loop.create_window(window_attributes) {
+            Ok(window) => Arc::new(window), // needed for resize closure on web
+            Err(e) => return self.fail(AppError::Window(e)),
+        };
         self.window = Some(window.clone());
+        self.pacer
+            .set_refresh_rate(window.current_monitor().and_then(|monitor| monitor.refresh_rate_millihertz()));
 
         #[cfg(target_arch = "wasm32")]
-        let size = {
-            use wasm_bindgen::JsCast;
-            use winit::platform::web::WindowExtWebSys;
-
-            // On wasm, append the canvas to the document body
-            let window = window.clone();
-            let canvas = window.canvas().expect("couldn't retrieve canvas");
-            let web_window = web_sys::window().expect("couldn't retrieve website window");
-            let body = web_window
-                .document()
-                .and_then(|doc| doc.body())
-                .expect("couldn't retrieve document body");
-            body.append_child(&web_sys::Element::from(canvas))
-                .ok()
-                .expect("couldn't append canvas to body");
-
-            let window_size = LogicalSize::new(
-                web_window.inner_width().unwrap().as_f64().unwrap() as u32,
-                web_window.inner_height().unwrap().as_f64().unwrap() as u32,
-            );
-            let _ = window.request_inner_size(window_size);
-
-            let resize_closure =
-                wasm_bindgen::closure::Closure::wrap(Box::new(move |_e: web_sys::Event| {
-                    let web_window = web_sys::window().expect("couldn't retrieve website window");
-                    log::info!("bres {} {}", body.client_width(), body.client_height());
-                    let _ = window.request_inner_size(LogicalSize::new(
-                        web_window.inner_width().unwrap().as_f64().unwrap(),
-                        web_window.inner_height().unwrap().as_f64().unwrap(),
-                    ));
-                }) as Box<dyn FnMut(_)>);
-            web_window
-                .add_event_listener_with_callback("resize", resize_closure.as_ref().unchecked_ref())
-                .unwrap();
-            resize_closure.forget();
-
-            window_size
+        let size = match attach_canvas(&window) {
+            Ok(size) => size,
+            Err(e) => return self.fail(e),
         };
 
-        let event_proxy = self.event_proxy.clone();
-        #[cfg(not(target_arch = "wasm32"))]
-        futures::executor::block_on(create_application(window, size, event_proxy));
-        #[cfg(target_arch = "wasm32")]
-        wasm_bindgen_futures::spawn_local(create_application(window, size, event_proxy));
+        self.spawn_application(window, size.into());
     }
 
     fn user_event(&mut self, _event_loop: &winit::event_loop::ActiveEventLoop, event: UserEvent) {
         match event {
+            // Creation is asynchronous on the web, so we might have been suspended in the meantime
+            UserEvent::ApplicationCreated(_) if self.suspended => {}
             UserEvent::ApplicationCreated(application) => {
                 self.app = Some(application);
             }
+            UserEvent::Failed(error) => self.fail(error),
         }
     }
 
+    fn suspended(&mut self, _event_loop: &winit::event_loop::ActiveEventLoop) {
+        // Mobile platforms destroy the native window behind our surface while suspended,
+        // so let go of everything that renders to it. `resumed` creates it all again.
+        log::info!("Suspended, releasing the application");
+        self.suspended = true;
+        self.app = None;
+    }
+
     fn window_event(
         &mut self,
         _event_loop: &winit::event_loop::ActiveEventLoop,
         _window_id: winit::window::WindowId,
         event: WindowEvent,
     ) {
+        if matches!(
+            event,
+            WindowEvent::KeyboardInput { .. }
+                | WindowEvent::MouseInput { .. }
+                | WindowEvent::MouseWheel { .. }
+                | WindowEvent::CursorMoved { .. }
+                | WindowEvent::Touch(_)
+                | WindowEvent::Resized(_)
+        ) {
+            self.pacer.input();
+        }
+        match event {
+            WindowEvent::Occluded(occluded) => self.pacer.set_occluded(occluded),
+            WindowEvent::Focused(focused) => self.pacer.set_focused(focused),
+            WindowEvent::Resized(size) => self.pacer.set_minimized(size.width == 0 || size.height == 0),
+            _ => {}
+        }
+
         let (Some(app), Some(window)) = (&mut self.app, &self.window) else {
             return;
         };
@@ -150,14 +291,53 @@ impl ApplicationHandler<UserEvent> for ApplicationWindow {
             WindowEvent::CloseRequested => {
                 self.close_requested = true;
             }
+            // The window may have moved to a monitor with a different refresh rate
+            WindowEvent::Moved(_) => {
+                let monitor = window.current_monitor();
+                self.pacer
+                    .set_refresh_rate(monitor.and_then(|monitor| monitor.refresh_rate_millihertz()));
+            }
             WindowEvent::RedrawRequested => {
-                if let Err(e) = app.render(&window) {
-                    if e == wgpu::SurfaceError::Outdated {
+                let start = Instant::now();
+                let result = app.render(window);
+                let draw_time = start.elapsed();
+                self.pacer.frame_drawn(draw_time);
+                if result.is_ok() {
+                    self.lost_frames = 0;
+                }
+                match result {
+                    Ok(()) => {
+                        if let Some(summary) = self.stats.frame(draw_time) {
+                            let language = self.config.ui.language();
+                            let title = language.text(Text::WindowTitle);
+                            window.set_title(&format!("{title} | {}", summary.describe(language)));
+                        }
+                    }
+                    Err(wgpu::SurfaceError::Outdated) => {
                         let size = window.inner_size();
                         app.resize(size.width, size.height);
-                    } else {
-                        panic!("{}", e);
                     }
+                    // Reconfiguring the surface is usually enough. If it keeps getting lost, the
+                    // device itself is probably gone (driver updates, TDR, ...), so start over
+                    // with a fresh Application, and give up if even that doesn't help.
+                    Err(e @ wgpu::SurfaceError::Lost) => {
+                        self.lost_frames += 1;
+                        if self.lost_frames >= MAX_LOST_FRAMES {
+                            self.fail(AppError::Render(e));
+                        } else if self.lost_frames % RECONFIGURE_ATTEMPTS == 0 {
+                            log::warn!("Surface lost again after reconfiguring, recreating the application");
+                            let window = window.clone();
+                            let size = window.inner_size().into();
+                            self.app = None;
+                            self.spawn_application(window, size);
+                        } else {
+                            log::warn!("Surface lost, reconfiguring it");
+                            let size = window.inner_size();
+                            app.resize(size.width, size.height);
+                        }
+                    }
+                    Err(wgpu::SurfaceError::Timeout) => log::warn!("Timed out acquiring a frame, skipping it"),
+                    Err(e @ wgpu::SurfaceError::OutOfMemory) => self.fail(AppError::Render(e)),
                 }
             }
             _ => {}
@@ -169,8 +349,31 @@ impl ApplicationHandler<UserEvent> for ApplicationWindow {
             event_loop.exit();
             return;
         }
-        if let Some(window) = &self.window {
-            window.request_redraw();
+        let Some(window) = &self.window else {
+            return;
+        };
+        if self.suspended {
+            event_loop.set_control_flow(ControlFlow::Wait);
+            return;
+        }
+        match self.pacer.next_frame() {
+            NextFrame::Now => {
+                window.request_redraw();
+                event_loop.set_control_flow(ControlFlow::Wait);
+            }
+            NextFrame::At(instant) => event_loop.set_control_flow(ControlFlow::WaitUntil(instant)),
+            NextFrame::Idle => {
+                // Don't leave the numbers of the last frames we drew in the title
+                if self.stats.pause() {
+                    let language = self.config.ui.language();
+                    window.set_title(&format!(
+                        "{} | {}",
+                        language.text(Text::WindowTitle),
+                        language.text(Text::Idle)
+                    ));
+                }
+                event_loop.set_control_flow(ControlFlow::Wait);
+            }
         }
     }
 }
@@ -0,0 +1,231 @@
+use std::time::Duration;
+
+use web_time::Instant;
+
+use crate::config::FrameConfig;
+
+/// Weight of the newest frame in the smoothed render time.
+const SMOOTHING: f64 = 0.1;
+/// Render time, as a fraction of the current frame interval, above which we drop to
+/// the next lower rate. Rendering includes waiting on vsync, so we allow some slack.
+const SLOWER_THRESHOLD: f64 = 1.1;
+/// Render time, as a fraction of the next shorter interval, below which we go back up.
+const FASTER_THRESHOLD: f64 = 0.75;
+
+/// What `ApplicationWindow::about_to_wait` should do about the next frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NextFrame {
+    /// Draw a frame right away
+    Now,
+    /// Don't draw yet, wake up again at the given time
+    At(Instant),
+    /// Nothing to draw until the user does something or the window is shown again
+    Idle,
+}
+
+/// Decides when frames are drawn, so we don't keep the GPU busy when nobody needs the frames.
+pub struct FramePacer {
+    max_fps_interval: Option<Duration>,
+    unfocused_interval: Option<Duration>,
+    idle_timeout: Option<Duration>,
+    match_refresh_rate: bool,
+    refresh_interval: Option<Duration>,
+    /// We draw on every `refresh_divisor`th refresh of the monitor
+    refresh_divisor: u32,
+    render_time: Option<f64>,
+    next_frame: Instant,
+    last_input: Instant,
+    occluded: bool,
+    minimized: bool,
+    focused: bool,
+}
+
+/// `Config::validate` makes sure `fps` isn't 0
+fn interval_for_fps(fps: Option<u32>) -> Option<Duration> {
+    fps.map(|fps| Duration::from_secs_f64(1.0 / fps as f64))
+}
+
+impl FramePacer {
+    pub fn new(config: &FrameConfig) -> Self {
+        let now = Instant::now();
+        Self {
+            max_fps_interval: interval_for_fps(config.max_fps),
+            unfocused_interval: interval_for_fps(config.unfocused_max_fps),
+            // Checked by `Config::validate`, so this can't panic
+            idle_timeout: config.idle_timeout.map(Duration::from_secs_f64),
+            match_refresh_rate: config.match_refresh_rate,
+            refresh_interval: None,
+            refresh_divisor: 1,
+            render_time: None,
+            next_frame: now,
+            last_input: now,
+            occluded: false,
+            minimized: false,
+            focused: true,
+        }
+    }
+
+    /// Note that the user interacted with the window, which ends any idling.
+    pub fn input(&mut self) {
+        self.last_input = Instant::now();
+    }
+
+    /// The window is completely covered by other windows (or otherwise not shown).
+    pub fn set_occluded(&mut self, occluded: bool) {
+        self.occluded = occluded;
+    }
+
+    /// Some platforms report minimizing as a resize to zero instead of occlusion.
+    pub fn set_minimized(&mut self, minimized: bool) {
+        self.minimized = minimized;
+    }
+
+    pub fn set_focused(&mut self, focused: bool) {
+        self.focused = focused;
+    }
+
+    /// Update the refresh rate of the monitor the window is on, `None` if unknown.
+    pub fn set_refresh_rate(&mut self, millihertz: Option<u32>) {
+        let interval = millihertz
+            .filter(|&mhz| mhz > 0)
+            .map(|mhz| Duration::from_secs_f64(1000.0 / mhz as f64));
+        if interval != self.refresh_interval {
+            if let Some(interval) = interval {
+                log::info!("Monitor refresh interval: {:.2} ms", interval.as_secs_f64() * 1000.0);
+            }
+            self.refresh_interval = interval;
+            self.refresh_divisor = 1;
+        }
+    }
+
+    /// Report how long drawing a frame took.
+    /// When pacing to the refresh rate, this picks the rate we can sustain, so frames stay
+    /// evenly spaced instead of alternating between one and two refreshes.
+    pub fn frame_drawn(&mut self, render_time: Duration) {
+        let render_time = render_time.as_secs_f64();
+        let smoothed = match self.render_time {
+            Some(previous) => previous + SMOOTHING * (render_time - previous),
+            None => render_time,
+        };
+        self.render_time = Some(smoothed);
+
+        let Some(refresh) = self.refresh_interval.filter(|_| self.match_refresh_rate) else {
+            return;
+        };
+        let refresh = refresh.as_secs_f64();
+        let divisor = self.refresh_divisor as f64;
+        if smoothed > refresh * divisor * SLOWER_THRESHOLD {
+            self.refresh_divisor += 1;
+        } else if self.refresh_divisor > 1 && smoothed < refresh * (divisor - 1.0) * FASTER_THRESHOLD {
+            self.refresh_divisor -= 1;
+        } else {
+            return;
+        }
+        log::debug!("Pacing frames to every {} monitor refresh(es)", self.refresh_divisor);
+    }
+
+    fn frame_interval(&self) -> Option<Duration> {
+        // At the full refresh rate, presenting (Fifo) already waits for every vblank.
+        // A timer on top of that would make us miss one whenever it wakes us up late.
+        let paced = self
+            .refresh_interval
+            .filter(|_| self.match_refresh_rate && self.refresh_divisor > 1)
+            .map(|interval| interval * self.refresh_divisor);
+        let unfocused = self.unfocused_interval.filter(|_| !self.focused);
+        [self.max_fps_interval, paced, unfocused].into_iter().flatten().max()
+    }
+
+    pub fn next_frame(&mut self) -> NextFrame {
+        // Nobody can see the frames, so don't spend any GPU time on them
+        if self.occluded || self.minimized {
+            return NextFrame::Idle;
+        }
+
+        let now = Instant::now();
+        if self
+            .idle_timeout
+            .is_some_and(|timeout| now.duration_since(self.last_input) > timeout)
+        {
+            return NextFrame::Idle;
+        }
+
+        let Some(interval) = self.frame_interval() else {
+            return NextFrame::Now;
+        };
+        if now < self.next_frame {
+            return NextFrame::At(self.next_frame);
+        }
+        // Keep a steady cadence, unless we fell behind by more than a frame (e.g. after idling)
+        self.next_frame += interval;
+        if self.next_frame < now {
+            self.next_frame = now + interval;
+        }
+        NextFrame::Now
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A pacer matching a 60 Hz monitor, whose refresh interval is ~16.7 ms
+    fn pacer_at_60hz() -> FramePacer {
+        let mut pacer = FramePacer::new(&FrameConfig {
+            match_refresh_rate: true,
+            ..Default::default()
+        });
+        pacer.set_refresh_rate(Some(60_000));
+        pacer
+    }
+
+    fn draw_frames(pacer: &mut FramePacer, millis: u64, count: usize) {
+        for _ in 0..count {
+            pacer.frame_drawn(Duration::from_millis(millis));
+        }
+    }
+
+    #[test]
+    fn full_refresh_rate_is_left_to_presentation() {
+        let mut pacer = pacer_at_60hz();
+        draw_frames(&mut pacer, 5, 10);
+        assert_eq!(pacer.refresh_divisor, 1);
+        assert_eq!(pacer.frame_interval(), None);
+    }
+
+    #[test]
+    fn slow_frames_lower_the_rate() {
+        let mut pacer = pacer_at_60hz();
+        draw_frames(&mut pacer, 25, 50);
+        assert_eq!(pacer.refresh_divisor, 2);
+        assert_eq!(pacer.frame_interval(), pacer.refresh_interval.map(|interval| interval * 2));
+    }
+
+    #[test]
+    fn fast_frames_raise_the_rate_again() {
+        let mut pacer = pacer_at_60hz();
+        draw_frames(&mut pacer, 25, 50);
+        assert_eq!(pacer.refresh_divisor, 2);
+        draw_frames(&mut pacer, 5, 50);
+        assert_eq!(pacer.refresh_divisor, 1);
+    }
+
+    #[test]
+    fn rate_holds_between_thresholds() {
+        let mut pacer = pacer_at_60hz();
+        // Slower than 0.75 refreshes, but well within two: not worth going back up
+        draw_frames(&mut pacer, 25, 50);
+        draw_frames(&mut pacer, 15, 200);
+        assert_eq!(pacer.refresh_divisor, 2);
+        // Just over one refresh is waiting on vsync, not a missed frame
+        let mut pacer = pacer_at_60hz();
+        draw_frames(&mut pacer, 17, 200);
+        assert_eq!(pacer.refresh_divisor, 1);
+    }
+
+    #[test]
+    fn very_slow_frames_skip_several_refreshes() {
+        let mut pacer = pacer_at_60hz();
+        draw_frames(&mut pacer, 45, 50);
+        assert_eq!(pacer.refresh_divisor, 3);
+    }
+}
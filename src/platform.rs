@@ -0,0 +1,40 @@
+// Small touches that make the raytracer feel at home on the desktop.
+use winit::window::Icon;
+
+const ICON_SIZE: u32 = 64;
+
+/// A lit sphere, drawn the same way the raytracer would: one ray per pixel,
+/// shaded with a single directional light.
+pub fn window_icon() -> Option<Icon> {
+    let light = {
+        let (x, y, z) = (-0.5f32, -0.6, 0.65);
+        let length = (x * x + y * y + z * z).sqrt();
+        [x / length, y / length, z / length]
+    };
+    let color = [0.81f32, 0.26, 0.17];
+
+    let mut rgba = Vec::with_capacity((ICON_SIZE * ICON_SIZE * 4) as usize);
+    for y in 0..ICON_SIZE {
+        for x in 0..ICON_SIZE {
+            // Pixel center in [-1, 1], with a little margin around the sphere
+            let px = ((x as f32 + 0.5) / ICON_SIZE as f32 * 2.0 - 1.0) * 1.05;
+            let py = ((y as f32 + 0.5) / ICON_SIZE as f32 * 2.0 - 1.0) * 1.05;
+            let d2 = px * px + py * py;
+            if d2 > 1.0 {
+                rgba.extend_from_slice(&[0, 0, 0, 0]);
+                continue;
+            }
+            let normal = [px, py, (1.0 - d2).sqrt()];
+            let diffuse = (normal[0] * light[0] + normal[1] * light[1] + normal[2] * light[2]).max(0.0);
+            let shade = 0.15 + 0.85 * diffuse;
+            for channel in color {
+                rgba.push(((channel * shade).powf(1.0 / 2.2) * 255.0) as u8);
+            }
+            rgba.push(255);
+        }
+    }
+
+    Icon::from_rgba(rgba, ICON_SIZE, ICON_SIZE)
+        .inspect_err(|e| log::warn!("Could not create window icon: {e}"))
+        .ok()
+}
@@ -0,0 +1,153 @@
+use std::time::Duration;
+
+use web_time::Instant;
+
+use crate::i18n::{Language, Text};
+
+/// How long frame timings are averaged before being reported.
+const PERIOD: Duration = Duration::from_secs(1);
+
+/// Frame timing averaged over the last measuring period.
+#[derive(Debug, Clone, Copy)]
+pub struct FrameSummary {
+    pub fps: f64,
+    /// Time spent in `Application::render`, which unlike the time between frames
+    /// doesn't include waiting for the next frame under `--max-fps` or idling
+    pub draw_average: Duration,
+    pub draw_worst: Duration,
+}
+
+impl FrameSummary {
+    pub fn describe(&self, language: Language) -> String {
+        format!(
+            "{:.0} FPS, {} {:.2} ms (max {:.2} ms)",
+            self.fps,
+            language.text(Text::DrawTime),
+            self.draw_average.as_secs_f64() * 1000.0,
+            self.draw_worst.as_secs_f64() * 1000.0
+        )
+    }
+}
+
+pub struct FrameStats {
+    period_start: Instant,
+    last_frame: Option<Instant>,
+    frames: u32,
+    draw_total: Duration,
+    draw_worst: Duration,
+}
+
+impl FrameStats {
+    pub fn new() -> Self {
+        Self {
+            period_start: Instant::now(),
+            last_frame: None,
+            frames: 0,
+            draw_total: Duration::ZERO,
+            draw_worst: Duration::ZERO,
+        }
+    }
+
+    /// Count a frame that took `draw_time` to draw, returning a summary whenever a measuring
+    /// period is complete.
+    pub fn frame(&mut self, draw_time: Duration) -> Option<FrameSummary> {
+        self.frame_at(Instant::now(), draw_time)
+    }
+
+    fn frame_at(&mut self, now: Instant, draw_time: Duration) -> Option<FrameSummary> {
+        let last_frame = self.last_frame.replace(now);
+        match last_frame.map(|last| now - last) {
+            // A gap this long means we weren't drawing (e.g. idling), don't count it
+            Some(frame_time) if frame_time < PERIOD => {
+                self.frames += 1;
+                self.draw_total += draw_time;
+                self.draw_worst = self.draw_worst.max(draw_time);
+            }
+            _ => {
+                self.restart(now);
+                return None;
+            }
+        }
+
+        let elapsed = now - self.period_start;
+        if elapsed < PERIOD {
+            return None;
+        }
+        let summary = FrameSummary {
+            fps: self.frames as f64 / elapsed.as_secs_f64(),
+            draw_average: self.draw_total / self.frames,
+            draw_worst: self.draw_worst,
+        };
+        self.restart(now);
+        Some(summary)
+    }
+
+    /// Drawing stopped, so the last summary no longer applies.
+    /// Returns whether we were measuring anything, i.e. whether the summary needs clearing.
+    pub fn pause(&mut self) -> bool {
+        self.last_frame.take().is_some()
+    }
+
+    fn restart(&mut self, now: Instant) {
+        self.period_start = now;
+        self.frames = 0;
+        self.draw_total = Duration::ZERO;
+        self.draw_worst = Duration::ZERO;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FRAME: Duration = Duration::from_millis(10);
+    const DRAW_TIME: Duration = Duration::from_millis(2);
+
+    /// Draw frames every `FRAME` from `start` on, returning the time of the last one and
+    /// the summaries that were produced.
+    fn draw_frames(stats: &mut FrameStats, start: Instant, count: u32) -> (Instant, Vec<FrameSummary>) {
+        let mut summaries = Vec::new();
+        let mut now = start;
+        for i in 0..count {
+            now = start + FRAME * i;
+            summaries.extend(stats.frame_at(now, DRAW_TIME));
+        }
+        (now, summaries)
+    }
+
+    #[test]
+    fn summary_only_after_period() {
+        let mut stats = FrameStats::new();
+        let start = Instant::now();
+        // The last of these frames is 990 ms after the first
+        let (last, summaries) = draw_frames(&mut stats, start, 100);
+        assert!(summaries.is_empty());
+
+        let summary = stats.frame_at(last + FRAME, DRAW_TIME).unwrap();
+        assert_eq!(summary.fps, 100.0);
+        assert_eq!(summary.draw_average, DRAW_TIME);
+        assert_eq!(summary.draw_worst, DRAW_TIME);
+    }
+
+    #[test]
+    fn first_frame_after_gap_is_not_counted() {
+        let mut stats = FrameStats::new();
+        let (last, _) = draw_frames(&mut stats, Instant::now(), 50);
+
+        // A slow frame after idling starts a new period instead of being measured
+        let resumed = last + PERIOD * 3;
+        assert!(stats.frame_at(resumed, Duration::from_millis(500)).is_none());
+        let (_, summaries) = draw_frames(&mut stats, resumed + FRAME, 100);
+        assert_eq!(summaries.len(), 1);
+        assert_eq!(summaries[0].draw_worst, DRAW_TIME);
+    }
+
+    #[test]
+    fn pause_clears_once() {
+        let mut stats = FrameStats::new();
+        assert!(!stats.pause());
+        draw_frames(&mut stats, Instant::now(), 5);
+        assert!(stats.pause());
+        assert!(!stats.pause());
+    }
+}